@@ -0,0 +1,26 @@
+//! Shared Cortex-M bit-band address arithmetic.
+//!
+//! Cortex-M3/M4 bit-banding mirrors each individual bit of the peripheral region
+//! (`0x4000_0000..0x4010_0000`) onto its own 32-bit word in the alias region starting at
+//! `0x4200_0000`, so a single bit can be set or cleared without a read-modify-write of its
+//! neighbors. Every backend in this crate that needs that (STM32F1 GPIO config, STM32F4 GPIO
+//! config, EXTI) uses the same two address computations, so they live here once.
+
+pub(crate) const PERIPHERALS_BASE: usize = 0x4000_0000;
+pub(crate) const PERIPHERALS_ALIAS: usize = 0x4200_0000;
+
+/// Bit-band alias of the whole word (or struct) at `port`.
+pub(crate) fn to_bitband_address<S, T>(port: &T) -> &'static S {
+    let byte_offset = (port as *const T as usize) - PERIPHERALS_BASE;
+    let address = PERIPHERALS_ALIAS + byte_offset * 32;
+    let ptr = address as *const S;
+    unsafe { &*ptr }
+}
+
+/// Bit-band alias of a single bit `bit` of the word at `register_addr`.
+pub(crate) fn to_bitband_bit_address<S>(register_addr: usize, bit: usize) -> &'static S {
+    let byte_offset = register_addr - PERIPHERALS_BASE;
+    let address = PERIPHERALS_ALIAS + byte_offset * 32 + bit * 4;
+    let ptr = address as *const S;
+    unsafe { &*ptr }
+}
@@ -0,0 +1,107 @@
+//! External interrupt (EXTI) configuration, wiring a GPIO pin to an EXTI line.
+//!
+//! Mirrors the edge-detect/interrupt-enable capability of the va108xx HAL's GPIO module, but for
+//! the AFIO/EXTI peripherals found on STM32F1. [`enable_exti`] programs `AFIO_EXTICR` to route the
+//! source port to the EXTI line matching `pin`, arms the rising/falling trigger bits in
+//! `EXTI.RTSR`/`EXTI.FTSR` and unmasks the line in `EXTI.IMR`; [`clear_pending`] acknowledges the
+//! interrupt by writing `EXTI.PR`. `AFIO_EXTICRx` has a 4-bit field per line and
+//! `RTSR`/`FTSR`/`IMR` one bit per line; all of these are written with single-bit bit-band
+//! stores, the same way the rest of the crate avoids read-modify-write on shared registers.
+//! `EXTI.PR` is cleared by writing `1`, so it is written directly with only the line's bit set
+//! (a bit-band store would write back every other pending bit as well and clear those too).
+//!
+//! ```rust,no_run
+//! # extern crate stm32_extras;
+//! # extern crate stm32f103xx;
+//! use stm32_extras::exti::{self, Edge, Port};
+//! # fn main() {
+//! let afio = unsafe { &*stm32f103xx::AFIO.get() };
+//! let exti = unsafe { &*stm32f103xx::EXTI.get() };
+//!
+//! // Fire an interrupt on both edges of PC13 (e.g. a button wired to that pin).
+//! exti::enable_exti(afio, exti, Port::C, 13, Edge::Both);
+//! # }
+//! ```
+
+extern crate stm32f103xx;
+extern crate vcell;
+
+use bitband::to_bitband_bit_address;
+use self::stm32f103xx::{AFIO, EXTI};
+use self::vcell::VolatileCell;
+
+/// GPIO port that can be routed to an EXTI line, matching the encoding of the `AFIO_EXTICRx`
+/// `EXTIx` fields (0 = GPIOA, 1 = GPIOB, and so on).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Port {
+    /// GPIOA
+    A = 0,
+    /// GPIOB
+    B = 1,
+    /// GPIOC
+    C = 2,
+    /// GPIOD
+    D = 3,
+    /// GPIOE
+    E = 4,
+    /// GPIOF
+    F = 5,
+    /// GPIOG
+    G = 6,
+}
+
+/// Edge(s) that should trigger an interrupt on an EXTI line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// Rising edge only.
+    Rising,
+    /// Falling edge only.
+    Falling,
+    /// Both rising and falling edges.
+    Both,
+}
+
+/// Route pin `pin` (0..16) of `port` to its matching EXTI line and arm it for `edge`.
+///
+/// Selects the source port in `AFIO_EXTICRx`, sets the trigger bits in `EXTI.RTSR`/`EXTI.FTSR`
+/// and unmasks the line in `EXTI.IMR`. Each bit of the line's `AFIO_EXTICRx` nibble and of
+/// `RTSR`/`FTSR`/`IMR` is written through its own bit-band store, so enabling one line never
+/// disturbs the other 15.
+pub fn enable_exti(afio: &AFIO, exti: &EXTI, port: Port, pin: usize, edge: Edge) {
+    assert!(pin < 16, "pin must be in the range 0..16, got {}", pin);
+
+    let exticr_addr = match pin / 4 {
+        0 => &afio.exticr1 as *const _ as usize,
+        1 => &afio.exticr2 as *const _ as usize,
+        2 => &afio.exticr3 as *const _ as usize,
+        _ => &afio.exticr4 as *const _ as usize,
+    };
+    let shift = (pin % 4) * 4;
+    let port_bits = port as u32;
+    for bit in 0..4 {
+        let cell: &VolatileCell<u32> = to_bitband_bit_address(exticr_addr, shift + bit);
+        cell.set((port_bits >> bit) & 1);
+    }
+
+    let rising = edge == Edge::Rising || edge == Edge::Both;
+    let falling = edge == Edge::Falling || edge == Edge::Both;
+
+    let rtsr_addr = &exti.rtsr as *const _ as usize;
+    let rtsr_bit: &VolatileCell<u32> = to_bitband_bit_address(rtsr_addr, pin);
+    rtsr_bit.set(if rising { 1 } else { 0 });
+
+    let ftsr_addr = &exti.ftsr as *const _ as usize;
+    let ftsr_bit: &VolatileCell<u32> = to_bitband_bit_address(ftsr_addr, pin);
+    ftsr_bit.set(if falling { 1 } else { 0 });
+
+    let imr_addr = &exti.imr as *const _ as usize;
+    let imr_bit: &VolatileCell<u32> = to_bitband_bit_address(imr_addr, pin);
+    imr_bit.set(1);
+}
+
+/// Clear the pending flag for `pin`'s EXTI line by writing `EXTI.PR`.
+pub fn clear_pending(exti: &EXTI, pin: usize) {
+    assert!(pin < 16, "pin must be in the range 0..16, got {}", pin);
+
+    exti.pr.write(|w| unsafe { w.bits(1 << pin) });
+}
@@ -0,0 +1,170 @@
+//! Driver for Hitachi HD44780-compatible character LCDs.
+//!
+//! The display is driven over any port implementing [`GPIOExtras`], using `write_pin_range` to
+//! update the whole data bus in one shot and `write_pin` for the RS and E pins, without disturbing
+//! unrelated pins on the same port.
+//!
+//! Since this crate is `no_std` and has no notion of a timer, callers provide their own busy-wait
+//! as a `delay_us` closure that is invoked with the number of microseconds to wait.
+//!
+//! ```rust,no_run
+//! # extern crate stm32_extras;
+//! # extern crate stm32f103xx;
+//! use stm32_extras::hd44780::{DataBus, Hd44780};
+//! # fn delay(_us: u32) {}
+//! # fn main() {
+//! let gpiob = unsafe { &*stm32f103xx::GPIOB.get() };
+//!
+//! // Data bus on pins 0..4 (0 to 3), RS on pin 4, E on pin 5.
+//! let lcd = Hd44780::new(gpiob, 0, DataBus::FourBit, 4, 5);
+//! lcd.init(&mut delay);
+//! lcd.write_char(b'A', &mut delay);
+//! # }
+//! ```
+
+use core::marker::PhantomData;
+use GPIOExtras;
+
+/// Width of the data bus used to talk to the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataBus {
+    /// 4 data lines (`data..data + 4`). Each byte is sent as two nibbles, high
+    /// nibble first.
+    FourBit,
+    /// 8 data lines (`data..data + 8`). Each byte is sent in a single transfer.
+    EightBit,
+}
+
+/// Starting DDRAM address for each of the (up to four) display rows.
+const ROW_OFFSETS: [u8; 4] = [0x00, 0x40, 0x14, 0x54];
+
+/// Driver for a HD44780-compatible character LCD attached to a `GPIOExtras` port.
+///
+/// `rs` and `e` are the bit offsets (within the port) of the register-select and enable pins;
+/// `data` is the bit offset of the first data pin, the rest of the bus following contiguously.
+pub struct Hd44780<'a, P: 'a, C> {
+    port: &'a P,
+    data: usize,
+    bus: DataBus,
+    rs: usize,
+    e: usize,
+    _config: PhantomData<C>,
+}
+
+impl<'a, P, C> Hd44780<'a, P, C>
+where
+    P: GPIOExtras<C>,
+{
+    /// Create a new driver instance. Does not touch any pins; call [`Hd44780::init`] before use.
+    pub fn new(port: &'a P, data: usize, bus: DataBus, rs: usize, e: usize) -> Self {
+        Hd44780 {
+            port,
+            data,
+            bus,
+            rs,
+            e,
+            _config: PhantomData,
+        }
+    }
+
+    /// Run the power-on initialization sequence: function set, display on, clear, entry mode.
+    ///
+    /// `delay_us` is called with the number of microseconds to busy-wait; the caller is
+    /// responsible for the actual waiting (this crate has no timer of its own).
+    pub fn init(&self, delay_us: &mut impl FnMut(u32)) {
+        // The controller may still be running its own power-on reset; give it time before
+        // sending the first command.
+        delay_us(15_000);
+
+        let function_set = match self.bus {
+            DataBus::FourBit => {
+                // A HD44780 always powers up expecting an 8-bit interface, so the first
+                // function-set can't be sent as a nibble pair yet: it would be misread as the
+                // high nibble of an 8-bit instruction. The standard "software reset" handshake
+                // below talks to it one high-nibble-only write at a time (bypassing the normal
+                // two-nibble `send`) until the last write switches it into 4-bit mode.
+                self.write_nibble(false, 0x3, delay_us);
+                delay_us(4_500);
+                self.write_nibble(false, 0x3, delay_us);
+                delay_us(150);
+                self.write_nibble(false, 0x3, delay_us);
+                delay_us(150);
+                self.write_nibble(false, 0x2, delay_us);
+                delay_us(150);
+                0x28
+            }
+            DataBus::EightBit => 0x38,
+        };
+        self.command(function_set, delay_us);
+        self.command(0x0c, delay_us); // Display on, cursor off, blink off.
+        self.clear(delay_us);
+        self.command(0x06, delay_us); // Entry mode: increment, no shift.
+    }
+
+    /// Send a command byte (RS low).
+    pub fn command(&self, cmd: u8, delay_us: &mut impl FnMut(u32)) {
+        self.send(false, cmd, delay_us);
+        delay_us(37);
+    }
+
+    /// Write a character to the display at the current cursor position (RS high).
+    pub fn write_char(&self, ch: u8, delay_us: &mut impl FnMut(u32)) {
+        self.send(true, ch, delay_us);
+        delay_us(37);
+    }
+
+    /// Clear the display and return the cursor to the home position.
+    pub fn clear(&self, delay_us: &mut impl FnMut(u32)) {
+        self.send(false, 0x01, delay_us);
+        delay_us(1500);
+    }
+
+    /// Move the cursor to `row`/`col` (both zero-based).
+    ///
+    /// `row` must be in the range `0..4` (the number of rows this driver has DDRAM offsets for);
+    /// out-of-range values panic.
+    pub fn set_cursor(&self, row: usize, col: usize, delay_us: &mut impl FnMut(u32)) {
+        assert!(
+            row < ROW_OFFSETS.len(),
+            "row must be in the range 0..{}, got {}",
+            ROW_OFFSETS.len(),
+            row
+        );
+        let address = ROW_OFFSETS[row] + col as u8;
+        self.command(0x80 | address, delay_us);
+    }
+
+    /// Write a single high-nibble-only value to the data pins and pulse `E`, without sending a
+    /// second (low) nibble. Used only for the power-on handshake in [`Hd44780::init`] that gets a
+    /// 4-bit bus into a known state before the normal two-nibble [`Hd44780::send`] can be used.
+    fn write_nibble(&self, rs: bool, nibble: u8, delay_us: &mut impl FnMut(u32)) {
+        self.port.write_pin(self.rs, rs);
+        self.port.write_pin_range(self.data, 4, u16::from(nibble));
+        self.pulse_enable(delay_us);
+    }
+
+    /// Strobe the enable pin: high, then low, latching whatever is currently on the data/RS pins.
+    fn pulse_enable(&self, delay_us: &mut impl FnMut(u32)) {
+        self.port.write_pin(self.e, true);
+        delay_us(1);
+        self.port.write_pin(self.e, false);
+    }
+
+    fn send(&self, rs: bool, value: u8, delay_us: &mut impl FnMut(u32)) {
+        self.port.write_pin(self.rs, rs);
+        match self.bus {
+            DataBus::EightBit => {
+                self.port.write_pin_range(self.data, 8, u16::from(value));
+                self.pulse_enable(delay_us);
+            }
+            DataBus::FourBit => {
+                self.port
+                    .write_pin_range(self.data, 4, u16::from(value >> 4));
+                self.pulse_enable(delay_us);
+                self.port
+                    .write_pin_range(self.data, 4, u16::from(value & 0x0f));
+                self.pulse_enable(delay_us);
+            }
+        }
+    }
+}
@@ -35,6 +35,17 @@
 #![deny(warnings)]
 #![no_std]
 
+pub mod hd44780;
+
+#[cfg(any(feature = "stm32f103xx", feature = "stm32f4"))]
+mod bitband;
+
+#[cfg(feature = "stm32f103xx")]
+pub mod exti;
+
+#[cfg(all(feature = "stm32f103xx", feature = "embedded-hal"))]
+pub mod pin;
+
 /// Convenient access to the bit blocks on GPIO ports.
 pub trait GPIOExtras<T> {
     /// Set `count` bits on the GPIO port starting from the bit number `offset`. Other bits are not
@@ -55,8 +66,8 @@ pub trait GPIOExtras<T> {
         self.read_pin_range(offset, 1) != 0
     }
 
-    /// Get access to configuration bits for `pin` of GPIO port.
-    fn pin_config(&self, pin: usize) -> &T;
+    /// Get a configuration builder for `pin` of GPIO port.
+    fn pin_config(&self, pin: usize) -> T;
 }
 
 /// Common features for STM32F1/STM32W1 series.
@@ -165,27 +176,295 @@ mod stm32f1xx {
             &self.config[pin]
         }
     }
+
+    /// Configuration builder for a single pin, returned by `pin_config`.
+    ///
+    /// Bundles the CRL/CRH configuration bits with the ODR bit-band word of the same pin, since
+    /// selecting pull-up vs. pull-down (`CNF=10`) requires driving ODR in addition to CNF/MODE.
+    pub struct GPIOPinConfig {
+        bits: &'static GPIOBitbandConfigBlock,
+        odr: &'static VolatileCell<u32>,
+    }
+
+    impl GPIOPinConfig {
+        /// Build a pin configuration from its CRL/CRH bits and its ODR bit-band word.
+        pub fn new(bits: &'static GPIOBitbandConfigBlock, odr: &'static VolatileCell<u32>) -> Self {
+            GPIOPinConfig { bits, odr }
+        }
+
+        /// Input mode (reset state)
+        pub fn input(&self) -> &Self {
+            self.bits.input();
+            self
+        }
+
+        /// Output mode, max speed 2 MHz.
+        pub fn output2(&self) -> &Self {
+            self.bits.output2();
+            self
+        }
+
+        /// Output mode, max speed 10 MHz.
+        pub fn output10(&self) -> &Self {
+            self.bits.output10();
+            self
+        }
+
+        /// Output mode, max speed 50 MHz.
+        pub fn output50(&self) -> &Self {
+            self.bits.output50();
+            self
+        }
+
+        /// Push-pull
+        pub fn push_pull(&self) -> &Self {
+            self.bits.push_pull();
+            self
+        }
+
+        /// Open-drain
+        pub fn open_drain(&self) -> &Self {
+            self.bits.open_drain();
+            self
+        }
+
+        /// General purpose
+        pub fn general(&self) -> &Self {
+            self.bits.general();
+            self
+        }
+
+        /// Alternate function
+        pub fn alternate(&self) -> &Self {
+            self.bits.alternate();
+            self
+        }
+
+        /// Analog mode
+        pub fn analog(&self) -> &Self {
+            self.bits.analog();
+            self
+        }
+
+        /// Floating input (reset state)
+        pub fn floating(&self) -> &Self {
+            self.bits.floating();
+            self
+        }
+
+        /// Input with pull-up, pulling the pin towards `VDD` when not driven.
+        ///
+        /// Sets `CNF=10` and drives the pin's ODR bit-band word to `1` in a single bit-band
+        /// store, so neighboring pins' output data bits are left untouched.
+        pub fn pull_up(&self) -> &Self {
+            self.bits.pull_up_down();
+            self.odr.set(1);
+            self
+        }
+
+        /// Input with pull-down, pulling the pin towards `VSS` when not driven.
+        ///
+        /// Sets `CNF=10` and drives the pin's ODR bit-band word to `0` in a single bit-band
+        /// store, so neighboring pins' output data bits are left untouched.
+        pub fn pull_down(&self) -> &Self {
+            self.bits.pull_up_down();
+            self.odr.set(0);
+            self
+        }
+
+        /// What this pin's output data (ODR) bit is currently set to.
+        ///
+        /// Unlike `GPIOExtras::read_pin`, which reads the electrically-sensed `IDR` value, this
+        /// reflects what the pin was last *driven* to — the value `StatefulOutputPin` needs, since
+        /// an open-drain output (or a push-pull pin fighting external contention) can read back a
+        /// different level on `IDR` than what was written.
+        pub fn is_set(&self) -> bool {
+            self.odr.get() != 0
+        }
+    }
 }
 
 #[cfg(feature = "stm32f103xx")]
 mod stm32f103 {
     extern crate stm32f103xx;
+    extern crate vcell;
     use self::stm32f103xx::gpioa;
+    use self::vcell::VolatileCell;
+    use bitband::{to_bitband_address, to_bitband_bit_address};
     use super::stm32f1xx::GPIOBitbandRegisterBlock;
-    use super::stm32f1xx::GPIOBitbandConfigBlock;
+    use super::stm32f1xx::GPIOPinConfig;
     use super::GPIOExtras;
 
-    const PERIPHERALS_BASE: usize = 0x4000_0000;
-    const PERIPHERALS_ALIAS: usize = 0x4200_0000;
+    impl GPIOExtras<GPIOPinConfig> for gpioa::RegisterBlock {
+        fn write_pin_range(&self, offset: usize, count: usize, data: u16) {
+            let mask = (1 << count) - 1;
+            let bits = u32::from(data & mask) | // Set '1's
+                (u32::from(!data & mask) << 16); // Clear '0's
+            self.bsrr.write(|w| unsafe { w.bits(bits << offset) });
+        }
+
+        fn read_pin_range(&self, offset: usize, count: usize) -> u16 {
+            let mask = (1 << count) - 1;
+            ((self.idr.read().bits() >> offset) as u16) & mask
+        }
+
+        fn pin_config(&self, pin: usize) -> GPIOPinConfig {
+            let registers: &GPIOBitbandRegisterBlock = to_bitband_address(self);
+            let odr_addr = &self.odr as *const _ as usize;
+            let odr: &VolatileCell<u32> = to_bitband_bit_address(odr_addr, pin);
+            GPIOPinConfig::new(registers.config(pin), odr)
+        }
+    }
+}
+/// Common features for STM32F4 series.
+#[cfg(feature = "stm32f4")]
+mod stm32f4xx {
+    extern crate vcell;
+    use self::vcell::VolatileCell;
 
-    fn to_bitband_address<S, T>(port: &T) -> &'static S {
-        let byte_offset = (port as *const T as usize) - PERIPHERALS_BASE;
-        let address = PERIPHERALS_ALIAS + byte_offset * 32;
-        let ptr = address as *const S;
-        unsafe { &*ptr }
+    /// Configuration builder for a single pin, returned by `pin_config`.
+    ///
+    /// Unlike STM32F1, STM32F4 keeps MODER/OTYPER/OSPEEDR/PUPDR/AFR as separate registers rather
+    /// than packing all of a pin's configuration bits together, so each field below is its own
+    /// bit-band word (or pair of words, for the 2-bit fields) instead of a single shared block.
+    pub struct GPIOPinConfig {
+        moder_low: &'static VolatileCell<u32>,
+        moder_high: &'static VolatileCell<u32>,
+        otyper: &'static VolatileCell<u32>,
+        ospeedr_low: &'static VolatileCell<u32>,
+        ospeedr_high: &'static VolatileCell<u32>,
+        pupdr_low: &'static VolatileCell<u32>,
+        pupdr_high: &'static VolatileCell<u32>,
+        afr: [&'static VolatileCell<u32>; 4],
     }
 
-    impl GPIOExtras<GPIOBitbandConfigBlock> for gpioa::RegisterBlock {
+    impl GPIOPinConfig {
+        /// Build a pin configuration from its MODER/OTYPER/OSPEEDR/PUPDR/AFR bit-band words.
+        pub fn new(
+            moder_low: &'static VolatileCell<u32>,
+            moder_high: &'static VolatileCell<u32>,
+            otyper: &'static VolatileCell<u32>,
+            ospeedr_low: &'static VolatileCell<u32>,
+            ospeedr_high: &'static VolatileCell<u32>,
+            pupdr_low: &'static VolatileCell<u32>,
+            pupdr_high: &'static VolatileCell<u32>,
+            afr: [&'static VolatileCell<u32>; 4],
+        ) -> Self {
+            GPIOPinConfig {
+                moder_low,
+                moder_high,
+                otyper,
+                ospeedr_low,
+                ospeedr_high,
+                pupdr_low,
+                pupdr_high,
+                afr,
+            }
+        }
+
+        /// Input mode (reset state)
+        pub fn input(&self) -> &Self {
+            self.moder_low.set(0);
+            self.moder_high.set(0);
+            self
+        }
+
+        /// General purpose output mode.
+        pub fn output(&self) -> &Self {
+            self.moder_low.set(1);
+            self.moder_high.set(0);
+            self
+        }
+
+        /// Analog mode.
+        pub fn analog(&self) -> &Self {
+            self.moder_low.set(1);
+            self.moder_high.set(1);
+            self
+        }
+
+        /// Alternate function mode, selecting alternate function number `af` (0..=15).
+        pub fn alternate(&self, af: u8) -> &Self {
+            self.moder_low.set(0);
+            self.moder_high.set(1);
+            for (i, bit) in self.afr.iter().enumerate() {
+                bit.set(u32::from((af >> i) & 1));
+            }
+            self
+        }
+
+        /// Push-pull output.
+        pub fn push_pull(&self) -> &Self {
+            self.otyper.set(0);
+            self
+        }
+
+        /// Open-drain output.
+        pub fn open_drain(&self) -> &Self {
+            self.otyper.set(1);
+            self
+        }
+
+        /// No pull-up/pull-down (reset state).
+        pub fn floating(&self) -> &Self {
+            self.pupdr_low.set(0);
+            self.pupdr_high.set(0);
+            self
+        }
+
+        /// Pull-up.
+        pub fn pull_up(&self) -> &Self {
+            self.pupdr_low.set(1);
+            self.pupdr_high.set(0);
+            self
+        }
+
+        /// Pull-down.
+        pub fn pull_down(&self) -> &Self {
+            self.pupdr_low.set(0);
+            self.pupdr_high.set(1);
+            self
+        }
+
+        /// Low speed (2 MHz).
+        pub fn speed_low(&self) -> &Self {
+            self.ospeedr_low.set(0);
+            self.ospeedr_high.set(0);
+            self
+        }
+
+        /// Medium speed (25 MHz).
+        pub fn speed_medium(&self) -> &Self {
+            self.ospeedr_low.set(1);
+            self.ospeedr_high.set(0);
+            self
+        }
+
+        /// Fast speed (50 MHz).
+        pub fn speed_fast(&self) -> &Self {
+            self.ospeedr_low.set(0);
+            self.ospeedr_high.set(1);
+            self
+        }
+
+        /// High speed (100 MHz).
+        pub fn speed_high(&self) -> &Self {
+            self.ospeedr_low.set(1);
+            self.ospeedr_high.set(1);
+            self
+        }
+    }
+}
+
+#[cfg(feature = "stm32f4")]
+mod stm32f407 {
+    extern crate stm32f407xx;
+    use self::stm32f407xx::gpioa;
+    use bitband::to_bitband_bit_address;
+    use super::stm32f4xx::GPIOPinConfig;
+    use super::GPIOExtras;
+
+    impl GPIOExtras<GPIOPinConfig> for gpioa::RegisterBlock {
         fn write_pin_range(&self, offset: usize, count: usize, data: u16) {
             let mask = (1 << count) - 1;
             let bits = u32::from(data & mask) | // Set '1's
@@ -198,9 +477,32 @@ mod stm32f103 {
             ((self.idr.read().bits() >> offset) as u16) & mask
         }
 
-        fn pin_config(&self, pin: usize) -> &GPIOBitbandConfigBlock {
-            let registers: &GPIOBitbandRegisterBlock = to_bitband_address(self);
-            &registers.config(pin)
+        fn pin_config(&self, pin: usize) -> GPIOPinConfig {
+            let moder_addr = &self.moder as *const _ as usize;
+            let otyper_addr = &self.otyper as *const _ as usize;
+            let ospeedr_addr = &self.ospeedr as *const _ as usize;
+            let pupdr_addr = &self.pupdr as *const _ as usize;
+            let (afr_addr, afr_pin) = if pin < 8 {
+                (&self.afrl as *const _ as usize, pin)
+            } else {
+                (&self.afrh as *const _ as usize, pin - 8)
+            };
+
+            GPIOPinConfig::new(
+                to_bitband_bit_address(moder_addr, pin * 2),
+                to_bitband_bit_address(moder_addr, pin * 2 + 1),
+                to_bitband_bit_address(otyper_addr, pin),
+                to_bitband_bit_address(ospeedr_addr, pin * 2),
+                to_bitband_bit_address(ospeedr_addr, pin * 2 + 1),
+                to_bitband_bit_address(pupdr_addr, pin * 2),
+                to_bitband_bit_address(pupdr_addr, pin * 2 + 1),
+                [
+                    to_bitband_bit_address(afr_addr, afr_pin * 4),
+                    to_bitband_bit_address(afr_addr, afr_pin * 4 + 1),
+                    to_bitband_bit_address(afr_addr, afr_pin * 4 + 2),
+                    to_bitband_bit_address(afr_addr, afr_pin * 4 + 3),
+                ],
+            )
         }
     }
-}
\ No newline at end of file
+}
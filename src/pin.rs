@@ -0,0 +1,190 @@
+//! Type-state pin wrappers built on top of [`GPIOExtras`].
+//!
+//! A [`Pin`] carries its electrical mode (input/output, pull, push-pull/open-drain) as part of
+//! its type, the same way the va108xx and stm32f4xx HALs do it. Mode changes go through
+//! `into_*` methods that consume `self` and hand back a `Pin` typed for the new mode, so the
+//! compiler rejects e.g. reading an output or writing an input. Mode switches are implemented in
+//! terms of the existing [`pin_config`](GPIOExtras::pin_config) builder, input reads and output
+//! writes go through `read_pin`/`write_pin`, so they keep the crate's zero-read-modify-write BSRR
+//! semantics, and the output state (`is_set_high`/`is_set_low`/`toggle`) is read from the pin's
+//! ODR bit.
+//!
+//! This module is currently STM32F1-only: `Pin`'s `into_*` methods are implemented directly in
+//! terms of the STM32F1 `stm32f1xx::GPIOPinConfig` builder, so they aren't available for the
+//! STM32F4 `GPIOExtras` backend even though both implement the same trait. Porting this wrapper
+//! to be generic over both backends' config builders is tracked as future work.
+//!
+//! ```rust,no_run
+//! # extern crate stm32_extras;
+//! # extern crate stm32f103xx;
+//! # extern crate embedded_hal;
+//! use embedded_hal::digital::v2::OutputPin;
+//! use stm32_extras::pin::Pin;
+//! # fn main() {
+//! let gpioc = unsafe { &*stm32f103xx::GPIOC.get() };
+//!
+//! let mut led = Pin::new(gpioc, 13).into_push_pull_output();
+//! led.set_high().unwrap();
+//! # }
+//! ```
+
+extern crate embedded_hal;
+
+use core::convert::Infallible;
+use core::marker::PhantomData;
+use self::embedded_hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin};
+use stm32f1xx::GPIOPinConfig;
+use GPIOExtras;
+
+/// Floating input mode (reset state).
+pub struct Floating;
+/// Input with pull-up/pull-down mode, pulled towards `VDD`.
+pub struct PullUp;
+/// Input with pull-up/pull-down mode, pulled towards ground.
+pub struct PullDown;
+/// Push-pull output mode.
+pub struct PushPull;
+/// Open-drain output mode.
+pub struct OpenDrain;
+
+/// Input mode, parameterized over the pull configuration.
+pub struct Input<MODE> {
+    _mode: PhantomData<MODE>,
+}
+
+/// Output mode, parameterized over push-pull/open-drain.
+pub struct Output<MODE> {
+    _mode: PhantomData<MODE>,
+}
+
+/// Analog mode (e.g. for ADC inputs).
+pub struct Analog;
+
+/// A single GPIO pin on a [`GPIOExtras`] port, carrying its mode as a type parameter.
+pub struct Pin<'a, P: 'a, MODE> {
+    port: &'a P,
+    index: usize,
+    _mode: PhantomData<MODE>,
+}
+
+impl<'a, P> Pin<'a, P, Input<Floating>>
+where
+    P: GPIOExtras<GPIOPinConfig>,
+{
+    /// Wrap pin `index` of `port`. The pin starts out as a floating input, matching the reset
+    /// state of the hardware.
+    pub fn new(port: &'a P, index: usize) -> Self {
+        Pin {
+            port,
+            index,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<'a, P, MODE> Pin<'a, P, MODE>
+where
+    P: GPIOExtras<GPIOPinConfig>,
+{
+    fn into_mode<NEW>(self) -> Pin<'a, P, NEW> {
+        Pin {
+            port: self.port,
+            index: self.index,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Configure the pin as a push-pull output (2 MHz).
+    pub fn into_push_pull_output(self) -> Pin<'a, P, Output<PushPull>> {
+        self.port.pin_config(self.index).output2().general().push_pull();
+        self.into_mode()
+    }
+
+    /// Configure the pin as an open-drain output (2 MHz).
+    pub fn into_open_drain_output(self) -> Pin<'a, P, Output<OpenDrain>> {
+        self.port.pin_config(self.index).output2().general().open_drain();
+        self.into_mode()
+    }
+
+    /// Configure the pin as a floating input.
+    pub fn into_floating_input(self) -> Pin<'a, P, Input<Floating>> {
+        self.port.pin_config(self.index).input().floating();
+        self.into_mode()
+    }
+
+    /// Configure the pin as a pull-up input.
+    pub fn into_pull_up_input(self) -> Pin<'a, P, Input<PullUp>> {
+        self.port.pin_config(self.index).input().pull_up();
+        self.into_mode()
+    }
+
+    /// Configure the pin as a pull-down input.
+    pub fn into_pull_down_input(self) -> Pin<'a, P, Input<PullDown>> {
+        self.port.pin_config(self.index).input().pull_down();
+        self.into_mode()
+    }
+
+    /// Configure the pin as an analog input.
+    pub fn into_analog(self) -> Pin<'a, P, Analog> {
+        self.port.pin_config(self.index).input().analog();
+        self.into_mode()
+    }
+}
+
+impl<'a, P, MODE> OutputPin for Pin<'a, P, Output<MODE>>
+where
+    P: GPIOExtras<GPIOPinConfig>,
+{
+    type Error = Infallible;
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.port.write_pin(self.index, true);
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.port.write_pin(self.index, false);
+        Ok(())
+    }
+}
+
+impl<'a, P, MODE> StatefulOutputPin for Pin<'a, P, Output<MODE>>
+where
+    P: GPIOExtras<GPIOPinConfig>,
+{
+    fn is_set_high(&self) -> Result<bool, Infallible> {
+        Ok(self.port.pin_config(self.index).is_set())
+    }
+
+    fn is_set_low(&self) -> Result<bool, Infallible> {
+        Ok(!self.port.pin_config(self.index).is_set())
+    }
+}
+
+impl<'a, P, MODE> ToggleableOutputPin for Pin<'a, P, Output<MODE>>
+where
+    P: GPIOExtras<GPIOPinConfig>,
+{
+    type Error = Infallible;
+
+    fn toggle(&mut self) -> Result<(), Infallible> {
+        let high = self.port.pin_config(self.index).is_set();
+        self.port.write_pin(self.index, !high);
+        Ok(())
+    }
+}
+
+impl<'a, P, MODE> InputPin for Pin<'a, P, Input<MODE>>
+where
+    P: GPIOExtras<GPIOPinConfig>,
+{
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Infallible> {
+        Ok(self.port.read_pin(self.index))
+    }
+
+    fn is_low(&self) -> Result<bool, Infallible> {
+        Ok(!self.port.read_pin(self.index))
+    }
+}